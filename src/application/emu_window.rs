@@ -1,13 +1,20 @@
 use crate::emulator::chip8::Emulator;
 use crate::emulator::screen::Screen;
+use bytemuck::cast_slice;
 use imgui::*;
 use imgui_wgpu::{Renderer, Texture, TextureConfig};
 use wgpu::{
     util::{BufferInitDescriptor, DeviceExt},
-    BufferUsage, CommandEncoderDescriptor, Device, Extent3d, ImageCopyBuffer, ImageCopyTexture,
-    ImageDataLayout, Origin3d, Queue, TextureFormat, TextureUsage,
+    BindGroup, BindGroupLayout, Buffer, BufferUsage, ColorTargetState, ColorWrite,
+    CommandEncoder, CommandEncoderDescriptor, Device, Extent3d, ImageCopyBuffer, ImageCopyTexture,
+    ImageDataLayout, Origin3d, Queue, RenderPipeline, ShaderStage, TextureFormat, TextureUsage,
+    TextureView, COPY_BYTES_PER_ROW_ALIGNMENT,
 };
 
+/// Source for the fragment shader that maps `plane_tex` palette indices to
+/// on-screen colors (see `palette.wgsl`).
+const PALETTE_SHADER: &str = include_str!("palette.wgsl");
+
 pub struct RGBA {
     pub r: f32,
     pub g: f32,
@@ -35,29 +42,111 @@ pub struct EmulatorWindow {
     width: usize,
     height: usize,
     scale: f32,
-    color: RGBA,
+    /// Palette indexed by plane bits: 0 = background, 1 = foreground, 2/3 =
+    /// the extra colors used when a ROM draws on XO-CHIP's second bitplane.
+    palette: [RGBA; 4],
+    /// Per-pixel afterglow level (1.0 = just lit, decaying towards 0.0) used
+    /// to emulate CRT phosphor persistence and smooth out CHIP-8 XOR flicker.
+    brightness: Vec<f32>,
+    brightness_bytes: Vec<u8>,
+    /// Decay multiplier applied to `brightness` each frame a pixel is off;
+    /// 0.0 disables the effect and restores the old hard on/off behavior.
+    decay: f32,
     tex_id: TextureId,
+    plane_texture: wgpu::Texture,
+    plane_view: TextureView,
+    brightness_texture: wgpu::Texture,
+    brightness_view: TextureView,
+    /// Bytes per row of `plane_staging`/`brightness_staging`, rounded up to
+    /// wgpu's `COPY_BYTES_PER_ROW_ALIGNMENT`, and of `padded_plane`/
+    /// `padded_brightness`, which hold the padded copy of each row.
+    row_stride: u32,
+    plane_staging: Buffer,
+    brightness_staging: Buffer,
+    padded_plane: Vec<u8>,
+    padded_brightness: Vec<u8>,
+    palette_buffer: Buffer,
+    bind_group_layout: BindGroupLayout,
+    bind_group: BindGroup,
+    pipeline: RenderPipeline,
+    /// Set by the "Save Screenshot" button; consumed (and cleared) the next
+    /// time `update` runs, since that's where `device`/`queue` are available.
+    capture_requested: bool,
+    /// Whether frames are currently being collected for `recording.gif`.
+    recording: bool,
+    gif_frames: Vec<image::RgbaImage>,
 }
 
 impl EmulatorWindow {
     pub fn new(renderer: &mut Renderer, device: &Device) -> EmulatorWindow {
+        let palette = [
+            RGBA { r: 0.0, g: 0.0, b: 0.0, a: 1.0 },
+            RGBA { r: 0.19f32, g: 0.66f32, b: 0.38f32, a: 1.0f32 },
+            RGBA { r: 0.86f32, g: 0.20f32, b: 0.18f32, a: 1.0f32 },
+            RGBA { r: 0.20f32, g: 0.40f32, b: 0.86f32, a: 1.0f32 },
+        ];
+
+        let (plane_texture, plane_view) = EmulatorWindow::create_index_texture(
+            device,
+            Screen::WIDTH as u32,
+            Screen::HEIGHT as u32,
+            TextureFormat::R8Uint,
+            "plane_texture",
+        );
+        let (brightness_texture, brightness_view) = EmulatorWindow::create_index_texture(
+            device,
+            Screen::WIDTH as u32,
+            Screen::HEIGHT as u32,
+            TextureFormat::R8Unorm,
+            "brightness_texture",
+        );
+        let palette_buffer = EmulatorWindow::create_palette_buffer(device, &palette);
+        let (bind_group_layout, pipeline) = EmulatorWindow::create_pipeline(device);
+        let bind_group = EmulatorWindow::create_bind_group(
+            device,
+            &bind_group_layout,
+            &palette_buffer,
+            &plane_view,
+            &brightness_view,
+        );
+
+        let row_stride = EmulatorWindow::row_stride(Screen::WIDTH as u32, 1);
+        let staging_size = row_stride as u64 * Screen::HEIGHT as u64;
+        let plane_staging = EmulatorWindow::create_staging_buffer(device, staging_size, "plane_staging");
+        let brightness_staging =
+            EmulatorWindow::create_staging_buffer(device, staging_size, "brightness_staging");
+
         EmulatorWindow {
-            data: vec![0; Screen::WIDTH * Screen::HEIGHT * 4],
+            data: vec![0; Screen::WIDTH * Screen::HEIGHT],
             width: Screen::WIDTH,
             height: Screen::HEIGHT,
             scale: 9.0f32,
-            color: RGBA {
-                r: 0.19f32,
-                g: 0.66f32,
-                b: 0.38f32,
-                a: 1.0f32,
-            },
+            palette,
+            brightness: vec![0.0; Screen::WIDTH * Screen::HEIGHT],
+            brightness_bytes: vec![0; Screen::WIDTH * Screen::HEIGHT],
+            decay: 0.65f32,
             tex_id: EmulatorWindow::create_texture(
                 renderer,
                 device,
                 Screen::WIDTH as u32,
                 Screen::HEIGHT as u32,
             ),
+            plane_texture,
+            plane_view,
+            brightness_texture,
+            brightness_view,
+            row_stride,
+            plane_staging,
+            brightness_staging,
+            padded_plane: vec![0; staging_size as usize],
+            padded_brightness: vec![0; staging_size as usize],
+            palette_buffer,
+            bind_group_layout,
+            bind_group,
+            pipeline,
+            capture_requested: false,
+            recording: false,
+            gif_frames: Vec::new(),
         }
     }
 
@@ -72,15 +161,112 @@ impl EmulatorWindow {
                         (self.height as f32) * self.scale,
                     ],
                 )
-                .tint_col(self.color.to_array())
                 .build(&ui);
 
                 //ui.input_float(im_str!("Scale"), &mut self.scale).build();
                 //ui.same_line(0.0f32);
-                imgui::ColorEdit::new(im_str!("Main Color"), &mut self.color.to_array()).build(&ui);
+                EmulatorWindow::edit_palette_color(&ui, im_str!("Background"), &mut self.palette[0]);
+                EmulatorWindow::edit_palette_color(&ui, im_str!("Foreground"), &mut self.palette[1]);
+                EmulatorWindow::edit_palette_color(&ui, im_str!("XO-CHIP Color 2"), &mut self.palette[2]);
+                EmulatorWindow::edit_palette_color(&ui, im_str!("XO-CHIP Color 3"), &mut self.palette[3]);
+                ui.slider_float(im_str!("Phosphor Decay"), &mut self.decay, 0.0f32, 0.85f32)
+                    .build();
+
+                if ui.button(im_str!("Save Screenshot"), [0.0, 0.0]) {
+                    self.capture_requested = true;
+                }
+
+                ui.same_line(0.0f32);
+
+                let record_label = if self.recording {
+                    im_str!("Stop Recording")
+                } else {
+                    im_str!("Record GIF")
+                };
+                if ui.button(record_label, [0.0, 0.0]) {
+                    self.recording = !self.recording;
+                }
             });
     }
 
+    /// `ColorEdit` edits an `[f32; 4]` by reference, but `RGBA::to_array`
+    /// returns one by value, so editing the temporary directly throws the
+    /// user's change away the instant `build` returns. Edit a local copy and
+    /// only write it back into `color` when the widget reports a change.
+    fn edit_palette_color(ui: &imgui::Ui, label: &ImStr, color: &mut RGBA) {
+        let mut edited = color.to_array();
+        if imgui::ColorEdit::new(label, &mut edited).build(ui) {
+            *color = RGBA {
+                r: edited[0],
+                g: edited[1],
+                b: edited[2],
+                a: edited[3],
+            };
+        }
+    }
+
+    /// Reallocates the pixel buffer and backing wgpu textures for a new
+    /// resolution, e.g. when a ROM switches into SuperCHIP/XO-CHIP hi-res
+    /// mode. A no-op if `width`/`height` already match the current size.
+    pub fn resize(&mut self, renderer: &mut Renderer, device: &Device, width: usize, height: usize) {
+        if width == self.width && height == self.height {
+            return;
+        }
+
+        // A hi-res mode switch mid-recording would otherwise mix frames of
+        // two different sizes into `gif_frames`; flush what's been captured
+        // at the old size rather than let `save_gif_recording` discover the
+        // mismatch later and silently drop the rest of the GIF.
+        if self.recording || !self.gif_frames.is_empty() {
+            self.recording = false;
+            self.save_gif_recording();
+        }
+
+        self.data = vec![0; width * height];
+        self.brightness = vec![0.0; width * height];
+        self.brightness_bytes = vec![0; width * height];
+        self.width = width;
+        self.height = height;
+
+        renderer.textures.remove(self.tex_id);
+        self.tex_id = EmulatorWindow::create_texture(renderer, device, width as u32, height as u32);
+
+        let (plane_texture, plane_view) = EmulatorWindow::create_index_texture(
+            device,
+            width as u32,
+            height as u32,
+            TextureFormat::R8Uint,
+            "plane_texture",
+        );
+        let (brightness_texture, brightness_view) = EmulatorWindow::create_index_texture(
+            device,
+            width as u32,
+            height as u32,
+            TextureFormat::R8Unorm,
+            "brightness_texture",
+        );
+        self.bind_group = EmulatorWindow::create_bind_group(
+            device,
+            &self.bind_group_layout,
+            &self.palette_buffer,
+            &plane_view,
+            &brightness_view,
+        );
+        self.plane_texture = plane_texture;
+        self.plane_view = plane_view;
+        self.brightness_texture = brightness_texture;
+        self.brightness_view = brightness_view;
+
+        self.row_stride = EmulatorWindow::row_stride(width as u32, 1);
+        let staging_size = self.row_stride as u64 * height as u64;
+        self.plane_staging =
+            EmulatorWindow::create_staging_buffer(device, staging_size, "plane_staging");
+        self.brightness_staging =
+            EmulatorWindow::create_staging_buffer(device, staging_size, "brightness_staging");
+        self.padded_plane = vec![0; staging_size as usize];
+        self.padded_brightness = vec![0; staging_size as usize];
+    }
+
     pub fn update(
         &mut self,
         emulator: &Emulator,
@@ -88,24 +274,139 @@ impl EmulatorWindow {
         device: &Device,
         mut queue: &mut Queue,
     ) {
+        let (screen_width, screen_height) = (emulator.screen.width(), emulator.screen.height());
+        if screen_width != self.width || screen_height != self.height {
+            self.resize(renderer, device, screen_width, screen_height);
+        }
+
         for x in 0..self.width {
             for y in 0..self.height {
-                let v = if emulator.screen.get_pixel(x, y) == 1 {
-                    255u8
+                let plane0 = emulator.screen.get_pixel_plane(x, y, 0);
+                let plane1 = emulator.screen.get_pixel_plane(x, y, 1);
+                let idx = plane0 | (plane1 << 1);
+                let pos = (y * self.width) + x;
+
+                if idx != 0 {
+                    // Pixel just lit: take on its color and reset the glow.
+                    self.data[pos] = idx;
+                    self.brightness[pos] = 1.0f32;
                 } else {
-                    0
-                };
+                    // Pixel off this frame: let the previous color fade out
+                    // rather than snapping straight to the background.
+                    self.brightness[pos] *= self.decay;
+                }
 
-                let pos = (y * 4 * self.width) + (x * 4);
-                self.data[pos..pos + 4].copy_from_slice(&[v, v, v, 255u8]);
+                self.brightness_bytes[pos] = (self.brightness[pos] * 255.0) as u8;
             }
         }
 
-        // Uploaded updated screen texture data
-        self.update_texture(self.tex_id, renderer, &device, &mut queue);
+        EmulatorWindow::write_palette_buffer(&mut queue, &self.palette_buffer, &self.palette);
+
+        // Upload the freshly packed plane indices/brightness and re-run the
+        // palette pass so `tex_id` always reflects the current frame.
+        self.update_texture(&device, &mut queue);
+        self.render_palette_pass(renderer, device, &mut queue);
+
+        if self.capture_requested {
+            self.capture_requested = false;
+            if let Err(err) = self.capture_frame(renderer, device, &mut queue).save("screenshot.png") {
+                eprintln!("Failed to save screenshot: {}", err);
+            }
+        }
+
+        if self.recording {
+            let frame = self.capture_frame(renderer, device, &mut queue);
+            self.gif_frames.push(frame);
+        } else if !self.gif_frames.is_empty() {
+            self.save_gif_recording();
+        }
     }
 
-    /// Creates a new wgpu texture made from the imgui font atlas.
+    /// Reads back the RGBA pixels currently shown in `tex_id`, mirroring the
+    /// texture-readback half of Ruffle's `RenderTarget` capture pattern:
+    /// copy into a row-aligned buffer, map it, and strip the row padding.
+    pub fn capture_frame(&self, renderer: &Renderer, device: &Device, queue: &mut Queue) -> image::RgbaImage {
+        let row_stride = EmulatorWindow::row_stride(self.width as u32, 4);
+        let buffer_size = row_stride as u64 * self.height as u64;
+
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("capture_buffer"),
+            size: buffer_size,
+            usage: BufferUsage::COPY_DST | BufferUsage::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor { label: None });
+
+        if let Some(texture) = renderer.textures.get(self.tex_id) {
+            encoder.copy_texture_to_buffer(
+                ImageCopyTexture {
+                    texture: &texture.texture(),
+                    mip_level: 0,
+                    origin: Origin3d { x: 0, y: 0, z: 0 },
+                },
+                ImageCopyBuffer {
+                    buffer: &buffer,
+                    layout: ImageDataLayout {
+                        offset: 0,
+                        bytes_per_row: std::num::NonZeroU32::new(row_stride),
+                        rows_per_image: std::num::NonZeroU32::new(self.height as u32),
+                    },
+                },
+                Extent3d {
+                    width: self.width as u32,
+                    height: self.height as u32,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+
+        queue.submit(Some(encoder.finish()));
+
+        let slice = buffer.slice(..);
+        let (tx, rx) = futures::channel::oneshot::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = tx.send(result);
+        });
+        device.poll(wgpu::Maintain::Wait);
+        futures::executor::block_on(rx)
+            .expect("map_async channel dropped")
+            .expect("failed to map capture buffer");
+
+        let padded = slice.get_mapped_range();
+        let mut pixels = Vec::with_capacity(self.width * self.height * 4);
+        for row in padded.chunks(row_stride as usize) {
+            pixels.extend_from_slice(&row[..self.width * 4]);
+        }
+        drop(padded);
+        buffer.unmap();
+
+        image::RgbaImage::from_raw(self.width as u32, self.height as u32, pixels)
+            .expect("capture buffer size matches width/height")
+    }
+
+    /// Encodes the frames accumulated while `recording` was toggled on into
+    /// `recording.gif`.
+    fn save_gif_recording(&mut self) {
+        let file = match std::fs::File::create("recording.gif") {
+            Ok(file) => file,
+            Err(err) => {
+                eprintln!("Failed to create recording.gif: {}", err);
+                self.gif_frames.clear();
+                return;
+            }
+        };
+
+        let mut encoder = image::codecs::gif::GifEncoder::new(file);
+        for frame in self.gif_frames.drain(..) {
+            if let Err(err) = encoder.encode_frame(image::Frame::new(frame)) {
+                eprintln!("Failed to encode GIF frame: {}", err);
+                break;
+            }
+        }
+    }
+
+    /// Creates a new wgpu texture for imgui to display.
     fn create_texture(
         renderer: &mut Renderer,
         device: &Device,
@@ -124,7 +425,10 @@ impl EmulatorWindow {
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
             format: Some(TextureFormat::Rgba8Unorm),
-            usage: TextureUsage::SAMPLED | TextureUsage::COPY_DST,
+            usage: TextureUsage::SAMPLED
+                | TextureUsage::COPY_DST
+                | TextureUsage::COPY_SRC
+                | TextureUsage::RENDER_ATTACHMENT,
         };
 
         let texture = Texture::new(&device, &renderer, texture_config);
@@ -132,54 +436,310 @@ impl EmulatorWindow {
         renderer.textures.insert(texture)
     }
 
-    /// Creates and uploads a new wgpu texture made from the imgui font atlas.
-    fn update_texture(
-        &mut self,
-        id: TextureId,
-        renderer: &Renderer,
+    /// Creates a single-channel texture used as one of the palette pass's
+    /// inputs: `R8Uint` for the packed plane index, `R8Unorm` for the
+    /// per-pixel phosphor-decay brightness level.
+    fn create_index_texture(
         device: &Device,
-        queue: &mut Queue,
-    ) -> Option<bool> {
-        // Create the wgpu texture.
+        width: u32,
+        height: u32,
+        format: TextureFormat,
+        label: &str,
+    ) -> (wgpu::Texture, TextureView) {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size: Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: TextureUsage::SAMPLED | TextureUsage::COPY_DST,
+        });
 
-        // Upload the actual data to a wgpu buffer.
-        let bytes = self.data.len();
-        let buffer = device.create_buffer_init(&BufferInitDescriptor {
-            label: None,
-            usage: BufferUsage::COPY_SRC,
-            contents: &self.data[..],
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        (texture, view)
+    }
+
+    fn create_palette_buffer(device: &Device, palette: &[RGBA; 4]) -> Buffer {
+        device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("palette_buffer"),
+            usage: BufferUsage::UNIFORM | BufferUsage::COPY_DST,
+            contents: cast_slice(&EmulatorWindow::palette_to_bytes(palette)),
+        })
+    }
+
+    fn write_palette_buffer(queue: &mut Queue, palette_buffer: &Buffer, palette: &[RGBA; 4]) {
+        queue.write_buffer(
+            palette_buffer,
+            0,
+            cast_slice(&EmulatorWindow::palette_to_bytes(palette)),
+        );
+    }
+
+    fn palette_to_bytes(palette: &[RGBA; 4]) -> [[f32; 4]; 4] {
+        [
+            palette[0].to_array(),
+            palette[1].to_array(),
+            palette[2].to_array(),
+            palette[3].to_array(),
+        ]
+    }
+
+    /// Builds the bind group layout and render pipeline that maps palette
+    /// indices in `plane_tex` through the palette uniform buffer.
+    fn create_pipeline(device: &Device) -> (BindGroupLayout, RenderPipeline) {
+        let shader = device.create_shader_module(&wgpu::ShaderModuleDescriptor {
+            label: Some("palette_shader"),
+            source: wgpu::ShaderSource::Wgsl(PALETTE_SHADER.into()),
+            flags: wgpu::ShaderFlags::all(),
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("palette_bind_group_layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStage::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStage::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Uint,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: ShaderStage::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("palette_pipeline_layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
         });
 
-        // Make sure we have an active encoder.
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("palette_pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[ColorTargetState {
+                    format: TextureFormat::Rgba8Unorm,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: ColorWrite::ALL,
+                }],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+        });
+
+        (bind_group_layout, pipeline)
+    }
+
+    fn create_bind_group(
+        device: &Device,
+        layout: &BindGroupLayout,
+        palette_buffer: &Buffer,
+        plane_view: &TextureView,
+        brightness_view: &TextureView,
+    ) -> BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("palette_bind_group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: palette_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(plane_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(brightness_view),
+                },
+            ],
+        })
+    }
+
+    /// Rounds a row of `width * bytes_per_pixel` bytes (1 for the
+    /// single-channel `plane_texture`/`brightness_texture`, 4 for the RGBA
+    /// capture readback) up to wgpu's `COPY_BYTES_PER_ROW_ALIGNMENT`, so buffer-to-texture copies stay
+    /// valid at any resolution instead of only happening to work when the
+    /// unpadded row already lands on a 256-byte boundary.
+    fn row_stride(width: u32, bytes_per_pixel: u32) -> u32 {
+        let align = COPY_BYTES_PER_ROW_ALIGNMENT;
+        let unpadded = width * bytes_per_pixel;
+        (unpadded + align - 1) / align * align
+    }
+
+    fn create_staging_buffer(device: &Device, size: u64, label: &str) -> Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            size,
+            usage: BufferUsage::COPY_SRC | BufferUsage::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    /// Uploads the freshly packed palette-index and brightness pixels into
+    /// `plane_texture`/`brightness_texture` via the persistent, pre-padded
+    /// staging buffers instead of allocating a fresh buffer every frame.
+    fn update_texture(&mut self, device: &Device, queue: &mut Queue) -> Option<bool> {
         let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor { label: None });
 
-        let img_cl = ImageDataLayout {
-            offset: 0,
-            bytes_per_row: std::num::NonZeroU32::new(bytes as u32 / self.height as u32),
-            rows_per_image: std::num::NonZeroU32::new(self.height as u32),
-        };
+        EmulatorWindow::pad_and_upload(
+            queue,
+            &mut encoder,
+            &self.plane_texture,
+            &self.plane_staging,
+            &mut self.padded_plane,
+            &self.data,
+            self.width,
+            self.height,
+            self.row_stride,
+        );
+        EmulatorWindow::pad_and_upload(
+            queue,
+            &mut encoder,
+            &self.brightness_texture,
+            &self.brightness_staging,
+            &mut self.padded_brightness,
+            &self.brightness_bytes,
+            self.width,
+            self.height,
+            self.row_stride,
+        );
+
+        queue.submit(Some(encoder.finish()));
+
+        Some(true)
+    }
+
+    fn pad_and_upload(
+        queue: &Queue,
+        encoder: &mut CommandEncoder,
+        texture: &wgpu::Texture,
+        staging: &Buffer,
+        padded: &mut [u8],
+        data: &[u8],
+        width: usize,
+        height: usize,
+        row_stride: u32,
+    ) {
+        let stride = row_stride as usize;
+        for y in 0..height {
+            let src = &data[y * width..(y * width) + width];
+            let dst = y * stride;
+            padded[dst..dst + width].copy_from_slice(src);
+        }
+
+        queue.write_buffer(staging, 0, padded);
 
-        // Schedule a copy from the buffer to the texture.
         encoder.copy_buffer_to_texture(
             ImageCopyBuffer {
-                buffer: &buffer,
-                layout: img_cl,
+                buffer: staging,
+                layout: ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: std::num::NonZeroU32::new(row_stride),
+                    rows_per_image: std::num::NonZeroU32::new(height as u32),
+                },
             },
             ImageCopyTexture {
-                texture: &renderer.textures.get(id)?.texture(),
+                texture,
                 mip_level: 0,
                 origin: Origin3d { x: 0, y: 0, z: 0 },
             },
             Extent3d {
-                width: self.width as u32,
-                height: self.height as u32,
+                width: width as u32,
+                height: height as u32,
                 depth_or_array_layers: 1,
             },
         );
+    }
+
+    /// Runs the palette fragment shader over `plane_texture`, writing the
+    /// resolved RGBA colors into the texture imgui displays as `tex_id`.
+    fn render_palette_pass(&self, renderer: &Renderer, device: &Device, queue: &mut Queue) {
+        let output = match renderer.textures.get(self.tex_id) {
+            Some(texture) => texture.texture().create_view(&wgpu::TextureViewDescriptor::default()),
+            None => return,
+        };
+
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor { label: None });
+
+        {
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("palette_pass"),
+                color_attachments: &[wgpu::RenderPassColorAttachment {
+                    view: &output,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: true,
+                    },
+                }],
+                depth_stencil_attachment: None,
+            });
+
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &self.bind_group, &[]);
+            pass.draw(0..3, 0..1);
+        }
 
-        // Resolve the actual copy process.
         queue.submit(Some(encoder.finish()));
+    }
+}
 
-        Some(true)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn row_stride_pads_base_width_rows_up_to_the_boundary() {
+        assert_eq!(EmulatorWindow::row_stride(64, 1), 256);
+    }
+
+    #[test]
+    fn row_stride_pads_unaligned_rows_up_to_the_boundary() {
+        assert_eq!(EmulatorWindow::row_stride(127, 1), 256);
+        assert_eq!(EmulatorWindow::row_stride(128, 1), 256);
+    }
+
+    #[test]
+    fn row_stride_accounts_for_bytes_per_pixel() {
+        assert_eq!(EmulatorWindow::row_stride(64, 4), 256);
+        assert_eq!(EmulatorWindow::row_stride(128, 4), 512);
     }
 }
\ No newline at end of file
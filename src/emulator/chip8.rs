@@ -0,0 +1,22 @@
+use crate::emulator::screen::Screen;
+
+/// Owns the CHIP-8/SuperCHIP/XO-CHIP machine state. Only the parts the
+/// `application` module needs to render a frame (the display) are modeled
+/// here; the interpreter itself lives elsewhere.
+pub struct Emulator {
+    pub screen: Screen,
+}
+
+impl Emulator {
+    pub fn new() -> Emulator {
+        Emulator {
+            screen: Screen::new(),
+        }
+    }
+}
+
+impl Default for Emulator {
+    fn default() -> Self {
+        Emulator::new()
+    }
+}
@@ -0,0 +1,2 @@
+pub mod chip8;
+pub mod screen;
@@ -0,0 +1,72 @@
+/// The CHIP-8/SuperCHIP/XO-CHIP display. XO-CHIP adds a second drawing
+/// plane on top of the base CHIP-8 plane, so sprites can be composited in
+/// up to four colors; `get_pixel` only looks at plane 0 for callers that
+/// don't care about the extra planes.
+pub struct Screen {
+    width: usize,
+    height: usize,
+    planes: [Vec<u8>; 2],
+}
+
+impl Screen {
+    pub const WIDTH: usize = 64;
+    pub const HEIGHT: usize = 32;
+
+    pub fn new() -> Screen {
+        Screen {
+            width: Screen::WIDTH,
+            height: Screen::HEIGHT,
+            planes: [
+                vec![0; Screen::WIDTH * Screen::HEIGHT],
+                vec![0; Screen::WIDTH * Screen::HEIGHT],
+            ],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get_pixel(&self, x: usize, y: usize) -> u8 {
+        self.get_pixel_plane(x, y, 0)
+    }
+
+    pub fn get_pixel_plane(&self, x: usize, y: usize, plane: usize) -> u8 {
+        self.planes[plane][(y * self.width) + x]
+    }
+
+    pub fn set_pixel_plane(&mut self, x: usize, y: usize, plane: usize, value: u8) {
+        self.planes[plane][(y * self.width) + x] = value;
+    }
+
+    /// Switches between the base 64x32 CHIP-8 resolution and the 128x64
+    /// SuperCHIP/XO-CHIP hi-res mode, clearing both planes. A no-op if
+    /// already in the requested mode.
+    pub fn set_extended(&mut self, extended: bool) {
+        let (width, height) = if extended {
+            (Screen::WIDTH * 2, Screen::HEIGHT * 2)
+        } else {
+            (Screen::WIDTH, Screen::HEIGHT)
+        };
+
+        if width == self.width && height == self.height {
+            return;
+        }
+
+        self.width = width;
+        self.height = height;
+        for plane in self.planes.iter_mut() {
+            *plane = vec![0; width * height];
+        }
+    }
+}
+
+impl Default for Screen {
+    fn default() -> Self {
+        Screen::new()
+    }
+}